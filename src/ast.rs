@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use crate::{
+    apply_function,
+    lexer::{parse as parse_lexems, Associativity, Lexem, Operator},
+    tokenizer::tokenize,
+    Error,
+};
+
+/// A parsed expression tree, reusable beyond one-shot evaluation: it can be
+/// inspected, pretty-printed, or evaluated more than once.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Number(f64),
+    BinaryOp {
+        op: Operator,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    /// Not produced by `parse_ast` today (the lexer folds leading signs into
+    /// `Number`/`Mul`), but available for hand-built trees.
+    UnaryNeg(Box<Expr>),
+    Call(String, Box<Expr>),
+    Var(String),
+}
+
+impl Expr {
+    /// Evaluates the expression against an empty environment.
+    pub fn eval(&self) -> Result<f64, Error> {
+        self.eval_with(&HashMap::new())
+    }
+
+    /// Evaluates the expression, resolving `Var` nodes against `env`.
+    pub fn eval_with(&self, env: &HashMap<String, f64>) -> Result<f64, Error> {
+        match self {
+            Expr::Number(num) => Ok(*num),
+            Expr::BinaryOp { op, lhs, rhs } => {
+                let a = lhs.eval_with(env)?;
+                let b = rhs.eval_with(env)?;
+                Ok(match op {
+                    Operator::Add => a + b,
+                    Operator::Sub => a - b,
+                    Operator::Mul => a * b,
+                    Operator::Div => a / b,
+                    Operator::Pow => a.powf(b),
+                })
+            }
+            Expr::UnaryNeg(inner) => Ok(-inner.eval_with(env)?),
+            Expr::Call(name, arg) => apply_function(name, arg.eval_with(env)?),
+            Expr::Var(name) => env
+                .get(name)
+                .copied()
+                .ok_or_else(|| Error::UnknownVariable(name.clone())),
+        }
+    }
+}
+
+/// Parses `expr` into an [`Expr`] tree via a precedence-climbing pass over
+/// the infix lexem stream, using [`Operator::priority`] and associativity
+/// for binding power.
+pub fn parse_ast(expr: &str) -> Result<Expr, Error> {
+    let tokens = tokenize(expr)?;
+    let lexems = parse_lexems(&tokens)?;
+    let mut parser = Parser {
+        lexems: &lexems,
+        pos: 0,
+    };
+
+    let expr = parser.parse_expr(0)?;
+    if parser.pos != parser.lexems.len() {
+        return Err(Error::EvalError);
+    }
+
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    lexems: &'a [Lexem],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Lexem> {
+        self.lexems.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<&'a Lexem> {
+        let lexem = self.lexems.get(self.pos);
+        if lexem.is_some() {
+            self.pos += 1;
+        }
+
+        lexem
+    }
+
+    fn expect(&mut self, expected: &Lexem) -> Result<(), Error> {
+        match self.advance() {
+            Some(lexem) if lexem == expected => Ok(()),
+            _ => Err(Error::EvalError),
+        }
+    }
+
+    fn parse_expr(&mut self, min_priority: u8) -> Result<Expr, Error> {
+        let mut lhs = self.parse_primary()?;
+
+        while let Some(Lexem::Operator(op)) = self.peek() {
+            let op = op.clone();
+            if op.priority() < min_priority {
+                break;
+            }
+
+            self.advance();
+            let next_min = match op.associativity() {
+                Associativity::Left => op.priority() + 1,
+                Associativity::Right => op.priority(),
+            };
+            let rhs = self.parse_expr(next_min)?;
+            lhs = Expr::BinaryOp {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, Error> {
+        match self.advance() {
+            Some(Lexem::Number(num)) => Ok(Expr::Number(*num)),
+            Some(Lexem::Variable(name)) => Ok(Expr::Var(name.clone())),
+            Some(Lexem::Function(name)) => {
+                let name = name.clone();
+                self.expect(&Lexem::ParOpen)?;
+                let arg = self.parse_expr(0)?;
+                self.expect(&Lexem::ParClose)?;
+                Ok(Expr::Call(name, Box::new(arg)))
+            }
+            Some(Lexem::ParOpen) => {
+                let inner = self.parse_expr(0)?;
+                self.expect(&Lexem::ParClose)?;
+                Ok(inner)
+            }
+            _ => Err(Error::EvalError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{parse_ast, Expr};
+    use crate::lexer::Operator;
+
+    #[test]
+    fn test_parse_ast_number() {
+        assert_eq!(parse_ast("42").unwrap(), Expr::Number(42.0));
+    }
+
+    #[test]
+    fn test_parse_ast_binary_op() {
+        assert_eq!(
+            parse_ast("1+2").unwrap(),
+            Expr::BinaryOp {
+                op: Operator::Add,
+                lhs: Box::new(Expr::Number(1.0)),
+                rhs: Box::new(Expr::Number(2.0)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ast_precedence() {
+        assert_eq!(
+            parse_ast("1+2*3").unwrap(),
+            Expr::BinaryOp {
+                op: Operator::Add,
+                lhs: Box::new(Expr::Number(1.0)),
+                rhs: Box::new(Expr::BinaryOp {
+                    op: Operator::Mul,
+                    lhs: Box::new(Expr::Number(2.0)),
+                    rhs: Box::new(Expr::Number(3.0)),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ast_right_associative_pow() {
+        assert_eq!(
+            parse_ast("2**3**2").unwrap(),
+            Expr::BinaryOp {
+                op: Operator::Pow,
+                lhs: Box::new(Expr::Number(2.0)),
+                rhs: Box::new(Expr::BinaryOp {
+                    op: Operator::Pow,
+                    lhs: Box::new(Expr::Number(3.0)),
+                    rhs: Box::new(Expr::Number(2.0)),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ast_call_and_parens() {
+        assert_eq!(
+            parse_ast("sqrt(16)").unwrap(),
+            Expr::Call("sqrt".to_string(), Box::new(Expr::Number(16.0)))
+        );
+        assert_eq!(
+            parse_ast("2*(3+4)").unwrap(),
+            Expr::BinaryOp {
+                op: Operator::Mul,
+                lhs: Box::new(Expr::Number(2.0)),
+                rhs: Box::new(Expr::BinaryOp {
+                    op: Operator::Add,
+                    lhs: Box::new(Expr::Number(3.0)),
+                    rhs: Box::new(Expr::Number(4.0)),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_expr_eval() {
+        assert_eq!(512.0, parse_ast("2**3**2").unwrap().eval().unwrap());
+        assert_eq!(4.0, parse_ast("sqrt(16)").unwrap().eval().unwrap());
+        assert_eq!(14.0, parse_ast("2*(3+4)").unwrap().eval().unwrap());
+    }
+
+    #[test]
+    fn test_expr_eval_with_variables() {
+        let mut env = HashMap::new();
+        env.insert("x".to_string(), 5.0);
+        assert_eq!(11.0, parse_ast("x*2+1").unwrap().eval_with(&env).unwrap());
+        assert!(parse_ast("y").unwrap().eval().is_err());
+    }
+
+    #[test]
+    fn test_expr_unary_neg_hand_built() {
+        let expr = Expr::UnaryNeg(Box::new(Expr::Number(5.0)));
+        assert_eq!(-5.0, expr.eval().unwrap());
+
+        let mut env = HashMap::new();
+        env.insert("x".to_string(), 3.0);
+        let expr = Expr::UnaryNeg(Box::new(Expr::Var("x".to_string())));
+        assert_eq!(-3.0, expr.eval_with(&env).unwrap());
+    }
+}
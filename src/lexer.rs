@@ -1,12 +1,14 @@
 use crate::{
     tokenizer::{Token, TokenStream},
-    Error,
+    Error, Span,
 };
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Lexem {
     Number(f64),
     Operator(Operator),
+    Function(String),
+    Variable(String),
     ParOpen,
     ParClose,
 }
@@ -20,6 +22,12 @@ pub enum Operator {
     Pow,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
 impl Operator {
     pub fn priority(&self) -> u8 {
         match self {
@@ -30,6 +38,16 @@ impl Operator {
             Operator::Pow => 2,
         }
     }
+
+    pub fn associativity(&self) -> Associativity {
+        match self {
+            Operator::Add => Associativity::Left,
+            Operator::Sub => Associativity::Left,
+            Operator::Mul => Associativity::Left,
+            Operator::Div => Associativity::Left,
+            Operator::Pow => Associativity::Right,
+        }
+    }
 }
 
 pub type LexemStream = Vec<Lexem>;
@@ -40,6 +58,7 @@ enum LexerState<'a> {
     LeadingSign(f64),
     Number { val: &'a str, sign: f64 },
     Operator(&'a str),
+    Identifier(&'a str),
     ParOpen,
     ParClose,
     End,
@@ -50,7 +69,8 @@ pub fn parse(tokens: &TokenStream) -> Result<LexemStream, Error> {
     let mut state = LexerState::Initial;
     let mut pars = 0;
 
-    for (pos, token) in tokens.iter().enumerate() {
+    for (token, span) in tokens.iter() {
+        let span = *span;
         state = match state {
             LexerState::Initial => match token {
                 Token::Whitespace(_) => LexerState::Initial,
@@ -58,36 +78,42 @@ pub fn parse(tokens: &TokenStream) -> Result<LexemStream, Error> {
                 Token::Operator(op) if *op == "+" || *op == "-" => {
                     LexerState::LeadingSign(if *op == "-" { -1.0 } else { 1.0 })
                 }
+                Token::Identifier(name) => LexerState::Identifier(name),
                 Token::ParOpen => LexerState::ParOpen,
-                _ => return Err(Error::LexerError(pos)),
+                _ => return Err(Error::LexerError(span)),
             },
             LexerState::LeadingSign(sign) => match token {
                 Token::Number(val) => LexerState::Number { val, sign },
                 Token::ParOpen => {
-                    lexems.push(Lexem::Number(sign.into()));
+                    lexems.push(Lexem::Number(sign));
                     lexems.push(Lexem::Operator(Operator::Mul));
                     LexerState::ParOpen
                 }
-                _ => return Err(Error::LexerError(pos)),
+                Token::Identifier(name) => {
+                    lexems.push(Lexem::Number(sign));
+                    lexems.push(Lexem::Operator(Operator::Mul));
+                    LexerState::Identifier(name)
+                }
+                _ => return Err(Error::LexerError(span)),
             },
             LexerState::Number { val, sign } => {
                 if let Token::Whitespace(_) = token {
-                    LexerState::Number { val, sign };
+                    continue;
                 }
 
-                let num: f64 = val.parse().map_err(|e| Error::ParseNumberError(e))?;
+                let num: f64 = val.parse().map_err(Error::ParseNumberError)?;
                 lexems.push(Lexem::Number(num * sign));
 
                 match token {
                     Token::Operator(op) => LexerState::Operator(op),
                     Token::ParClose => LexerState::ParClose,
                     Token::End => LexerState::End,
-                    _ => return Err(Error::LexerError(pos)),
+                    _ => return Err(Error::LexerError(span)),
                 }
             }
             LexerState::Operator(op) => {
                 if let Token::Whitespace(_) = token {
-                    LexerState::Operator(op);
+                    continue;
                 }
 
                 match op {
@@ -101,8 +127,30 @@ pub fn parse(tokens: &TokenStream) -> Result<LexemStream, Error> {
 
                 match token {
                     Token::Number(val) => LexerState::Number { val, sign: 1.0 },
+                    Token::Identifier(name) => LexerState::Identifier(name),
                     Token::ParOpen => LexerState::ParOpen,
-                    _ => return Err(Error::LexerError(pos)),
+                    _ => return Err(Error::LexerError(span)),
+                }
+            }
+            LexerState::Identifier(name) => {
+                if let Token::Whitespace(_) = token {
+                    continue;
+                }
+
+                match token {
+                    Token::ParOpen => {
+                        lexems.push(Lexem::Function(name.to_string()));
+                        LexerState::ParOpen
+                    }
+                    _ => {
+                        lexems.push(Lexem::Variable(name.to_string()));
+                        match token {
+                            Token::Operator(op) => LexerState::Operator(op),
+                            Token::ParClose => LexerState::ParClose,
+                            Token::End => LexerState::End,
+                            _ => return Err(Error::LexerError(span)),
+                        }
+                    }
                 }
             }
             LexerState::ParOpen => {
@@ -117,8 +165,9 @@ pub fn parse(tokens: &TokenStream) -> Result<LexemStream, Error> {
                         LexerState::LeadingSign(if *op == "-" { -1.0 } else { 1.0 })
                     }
                     Token::Number(val) => LexerState::Number { val, sign: 1.0 },
+                    Token::Identifier(name) => LexerState::Identifier(name),
                     Token::ParOpen => LexerState::ParOpen,
-                    _ => return Err(Error::LexerError(pos)),
+                    _ => return Err(Error::LexerError(span)),
                 }
             }
             LexerState::ParClose => {
@@ -133,15 +182,19 @@ pub fn parse(tokens: &TokenStream) -> Result<LexemStream, Error> {
                     Token::Operator(op) => LexerState::Operator(op),
                     Token::ParClose => LexerState::ParClose,
                     Token::End => LexerState::End,
-                    _ => return Err(Error::LexerError(pos)),
+                    _ => return Err(Error::LexerError(span)),
                 }
             }
-            LexerState::End => return Err(Error::LexerError(pos)),
+            LexerState::End => return Err(Error::LexerError(span)),
         }
     }
 
     if state != LexerState::End || pars != 0 {
-        Err(Error::LexerError(tokens.len()))
+        let span = tokens
+            .last()
+            .map(|(_, span)| *span)
+            .unwrap_or(Span::new(0, 0));
+        Err(Error::LexerError(span))
     } else {
         Ok(lexems)
     }
@@ -242,6 +295,62 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_lexer_function() {
+        let lexems = parse(&tokenize("sqrt(16)").unwrap()).unwrap();
+        assert_eq!(
+            lexems,
+            vec![
+                Lexem::Function("sqrt".to_string()),
+                Lexem::ParOpen,
+                Lexem::Number(16.0),
+                Lexem::ParClose,
+            ]
+        )
+    }
+
+    #[test]
+    fn test_lexer_variable() {
+        let lexems = parse(&tokenize("x+1").unwrap()).unwrap();
+        assert_eq!(
+            lexems,
+            vec![
+                Lexem::Variable("x".to_string()),
+                Lexem::Operator(Operator::Add),
+                Lexem::Number(1.0),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_leading_minus_before_function() {
+        let lexems = parse(&tokenize("-sqrt(4)").unwrap()).unwrap();
+        assert_eq!(
+            lexems,
+            vec![
+                Lexem::Number(-1.0),
+                Lexem::Operator(Operator::Mul),
+                Lexem::Function("sqrt".to_string()),
+                Lexem::ParOpen,
+                Lexem::Number(4.0),
+                Lexem::ParClose,
+            ]
+        )
+    }
+
+    #[test]
+    fn test_leading_minus_before_variable() {
+        let lexems = parse(&tokenize("-x").unwrap()).unwrap();
+        assert_eq!(
+            lexems,
+            vec![
+                Lexem::Number(-1.0),
+                Lexem::Operator(Operator::Mul),
+                Lexem::Variable("x".to_string()),
+            ]
+        )
+    }
+
     #[test]
     fn test_parse_errors() {
         assert!(parse(&tokenize("2+").unwrap()).is_err());
@@ -1,26 +1,119 @@
+mod ast;
 mod lexer;
-mod tokenizer;
+pub mod tokenizer;
 
-use lexer::{parse, Lexem, LexemStream, Operator};
+pub use ast::{parse_ast, Expr};
+pub use tokenizer::{Lexer, Token, TokenStream};
+
+use lexer::{parse, Associativity, Lexem, LexemStream, Operator};
+use std::collections::HashMap;
+use std::fmt;
 use std::num::ParseFloatError;
 use thiserror::Error;
 use tokenizer::tokenize;
 
+/// A byte-offset range `[start, end)` into the original input, carried by
+/// tokens so errors can point at the exact offending text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Unexpected token at {0}")]
-    TokenizerError(usize),
+    TokenizerError(Span),
     #[error("Unexpected lexem at {0}")]
-    LexerError(usize),
+    LexerError(Span),
     #[error("Failed to parse a number")]
     ParseNumberError(#[from] ParseFloatError),
     #[error("Failed to parse operator {0}")]
     ParseOperatorError(String),
+    #[error("Unknown variable {0}")]
+    UnknownVariable(String),
     #[error("Failed to evaluate expression")]
     EvalError,
 }
 
+impl Error {
+    /// The span of the input this error refers to, when one is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::TokenizerError(span) => Some(*span),
+            Error::LexerError(span) => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+/// Renders a two-line diagnostic: `input` followed by a caret underline
+/// under the span the error points at. Returns `None` if the error carries
+/// no span.
+pub fn render_diagnostic(input: &str, error: &Error) -> Option<String> {
+    let span = error.span()?;
+    let input = input.trim_end_matches(['\r', '\n']);
+    let end = span.end.max(span.start + 1);
+    let underline = format!("{}{}", " ".repeat(span.start), "^".repeat(end - span.start));
+    Some(format!("{input}\n{underline}"))
+}
+
+type FunctionTable = &'static [(&'static str, fn(f64) -> f64)];
+
+const FUNCTIONS: FunctionTable = &[
+    ("sin", f64::sin),
+    ("cos", f64::cos),
+    ("sqrt", f64::sqrt),
+    ("ln", f64::ln),
+    ("abs", f64::abs),
+];
+
+pub(crate) fn apply_function(name: &str, arg: f64) -> Result<f64, Error> {
+    FUNCTIONS
+        .iter()
+        .find(|(fname, _)| *fname == name)
+        .map(|(_, f)| f(arg))
+        .ok_or_else(|| Error::ParseOperatorError(name.to_string()))
+}
+
 pub fn eval(expr: &str) -> Result<f64, Error> {
+    eval_with(expr, &mut HashMap::new())?.ok_or(Error::EvalError)
+}
+
+pub fn eval_with(expr: &str, env: &mut HashMap<String, f64>) -> Result<Option<f64>, Error> {
+    if let Some((name, rhs)) = split_assignment(expr) {
+        let value = eval_expr(rhs, env)?;
+        env.insert(name.to_string(), value);
+        Ok(None)
+    } else {
+        eval_expr(expr, env).map(Some)
+    }
+}
+
+fn split_assignment(expr: &str) -> Option<(&str, &str)> {
+    let trimmed = expr.trim();
+    let eq_pos = trimmed.find('=')?;
+    let name = trimmed[..eq_pos].trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_alphabetic()) {
+        return None;
+    }
+
+    Some((name, &trimmed[eq_pos + 1..]))
+}
+
+fn eval_expr(expr: &str, env: &HashMap<String, f64>) -> Result<f64, Error> {
     let tokens = tokenize(expr)?;
     let lexems = parse(&tokens)?;
     let postfix = postfix_repr(&lexems);
@@ -40,6 +133,16 @@ pub fn eval(expr: &str) -> Result<f64, Error> {
                     Operator::Pow => stack.push(a.powf(b)),
                 }
             }
+            Lexem::Function(name) => {
+                let arg = stack.pop().ok_or(Error::EvalError)?;
+                stack.push(apply_function(name, arg)?);
+            }
+            Lexem::Variable(name) => {
+                let value = *env
+                    .get(name)
+                    .ok_or_else(|| Error::UnknownVariable(name.clone()))?;
+                stack.push(value);
+            }
             Lexem::ParOpen => return Err(Error::EvalError),
             Lexem::ParClose => return Err(Error::EvalError),
         }
@@ -55,9 +158,16 @@ fn postfix_repr(infix: &LexemStream) -> LexemStream {
     for lexem in infix.iter() {
         match lexem {
             Lexem::Number(_) => postfix.push(lexem.clone()),
+            Lexem::Variable(_) => postfix.push(lexem.clone()),
+            Lexem::Function(_) => stack.push(lexem.clone()),
             Lexem::Operator(op) => {
                 while let Some(Lexem::Operator(cur)) = stack.last() {
-                    if cur.priority() >= op.priority() {
+                    let should_pop = match op.associativity() {
+                        Associativity::Left => cur.priority() >= op.priority(),
+                        Associativity::Right => cur.priority() > op.priority(),
+                    };
+
+                    if should_pop {
                         postfix.push(stack.pop().unwrap());
                     } else {
                         break;
@@ -75,6 +185,10 @@ fn postfix_repr(infix: &LexemStream) -> LexemStream {
 
                     postfix.push(top);
                 }
+
+                if let Some(Lexem::Function(_)) = stack.last() {
+                    postfix.push(stack.pop().unwrap());
+                }
             }
         }
     }
@@ -90,8 +204,10 @@ fn postfix_repr(infix: &LexemStream) -> LexemStream {
 mod tests {
     use std::vec;
 
+    use std::collections::HashMap;
+
     use crate::{
-        eval,
+        eval, eval_with, render_diagnostic,
         lexer::{parse, Lexem, Operator},
         postfix_repr,
         tokenizer::tokenize,
@@ -149,4 +265,93 @@ mod tests {
         assert_eq!(300.0, eval("2*(100+50)").unwrap());
         assert_eq!(-1.0, eval("-5+4").unwrap());
     }
+
+    #[test]
+    fn test_eval_pow_right_associative() {
+        assert_eq!(512.0, eval("2**3**2").unwrap());
+        assert_eq!(12.0, eval("2**2*3").unwrap());
+    }
+
+    #[test]
+    fn test_eval_functions() {
+        assert_eq!(4.0, eval("sqrt(16)").unwrap());
+        assert_eq!(0.0, eval("sin(0)").unwrap());
+        assert_eq!(2.0, eval("abs(-2)").unwrap());
+        assert_eq!(1.0, eval("ln(2.718281828459045)").unwrap());
+        assert_eq!(10.0, eval("sqrt(16)+sqrt(36)").unwrap());
+        assert!(eval("foo(1)").is_err());
+    }
+
+    #[test]
+    fn test_eval_leading_minus_before_function_or_variable() {
+        assert_eq!(-2.0, eval("-sqrt(4)").unwrap());
+
+        let mut env = HashMap::new();
+        assert_eq!(None, eval_with("x = 3", &mut env).unwrap());
+        assert_eq!(Some(-3.0), eval_with("-x", &mut env).unwrap());
+    }
+
+    #[test]
+    fn test_postfix_repr_function() {
+        let postfix = postfix_repr(&parse(&tokenize("sqrt(16)+4").unwrap()).unwrap());
+        assert_eq!(
+            postfix,
+            vec![
+                Lexem::Number(16.0),
+                Lexem::Function("sqrt".to_string()),
+                Lexem::Number(4.0),
+                Lexem::Operator(Operator::Add),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_eval_with_assignment_and_variables() {
+        let mut env = HashMap::new();
+        assert_eq!(None, eval_with("x = 3 + 4", &mut env).unwrap());
+        assert_eq!(Some(11.0), eval_with("x + 4", &mut env).unwrap());
+
+        assert_eq!(None, eval_with("y = x * 2", &mut env).unwrap());
+        assert_eq!(Some(14.0), eval_with("y", &mut env).unwrap());
+    }
+
+    #[test]
+    fn test_eval_with_unknown_variable() {
+        let mut env = HashMap::new();
+        assert!(matches!(
+            eval_with("x + 1", &mut env),
+            Err(crate::Error::UnknownVariable(ref name)) if name == "x"
+        ));
+    }
+
+    #[test]
+    fn test_postfix_repr_pow_right_associative() {
+        let postfix = postfix_repr(&parse(&tokenize("2**3**2").unwrap()).unwrap());
+        assert_eq!(
+            postfix,
+            vec![
+                Lexem::Number(2.0),
+                Lexem::Number(3.0),
+                Lexem::Number(2.0),
+                Lexem::Operator(Operator::Pow),
+                Lexem::Operator(Operator::Pow),
+            ]
+        )
+    }
+
+    #[test]
+    fn test_render_diagnostic_points_at_bad_char() {
+        let input = "1+2+@";
+        let err = eval(input).unwrap_err();
+        assert_eq!(
+            render_diagnostic(input, &err),
+            Some("1+2+@\n    ^".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render_diagnostic_none_without_span() {
+        let err = eval("sqrt(16)/0+foo").unwrap_err();
+        assert_eq!(render_diagnostic("sqrt(16)/0+foo", &err), None);
+    }
 }
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::io::Write;
 
 fn main() {
+    let mut env = HashMap::new();
+
     loop {
         print!("[>] ");
         std::io::stdout().flush().unwrap();
@@ -11,9 +14,15 @@ fn main() {
             break;
         }
 
-        match simplecalc::eval(&input) {
-            Ok(res) => println!("[=] {res}"),
-            Err(e) => println!("[E] {e}"),
+        match simplecalc::eval_with(&input, &mut env) {
+            Ok(Some(res)) => println!("[=] {res}"),
+            Ok(None) => {}
+            Err(e) => {
+                println!("[E] {e}");
+                if let Some(diagnostic) = simplecalc::render_diagnostic(&input, &e) {
+                    println!("{diagnostic}");
+                }
+            }
         }
 
         println!();
@@ -1,87 +1,144 @@
-use crate::Error;
+use crate::{Error, Span};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Token<'a> {
     Number(&'a str),
     Operator(&'a str),
+    Identifier(&'a str),
     Whitespace(&'a str),
     ParOpen,
     ParClose,
     End,
 }
 
-pub type TokenStream<'a> = Vec<Token<'a>>;
+pub type TokenStream<'a> = Vec<(Token<'a>, Span)>;
 
-#[derive(Debug, PartialEq)]
-enum TokenizerState {
-    Initial,
-    ParseNumber,
-    ParseOperator,
-    ParseWhitespace,
-    ParOpen(usize),
-    ParClose(usize),
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum CharClass {
+    Number,
+    Operator,
+    Identifier,
+    Whitespace,
+    ParOpen,
+    ParClose,
 }
 
-pub fn tokenize(input: &str) -> Result<TokenStream, Error> {
-    let mut tokens = TokenStream::new();
-    let mut state = TokenizerState::Initial;
-    let mut token_start = 0;
-
-    for (pos, ch) in input.chars().enumerate() {
-        let next_state = match ch {
-            _ if ch.is_digit(10) || ch == '.' => TokenizerState::ParseNumber,
-            _ if ch.is_whitespace() => TokenizerState::ParseWhitespace,
-            _ if ch == '+' || ch == '-' || ch == '*' || ch == '/' => TokenizerState::ParseOperator,
-            '(' => TokenizerState::ParOpen(pos),
-            ')' => TokenizerState::ParClose(pos),
-            _ => return Err(Error::TokenizerError(pos, input.chars().nth(pos).unwrap())),
-        };
+fn classify(ch: char) -> Option<CharClass> {
+    match ch {
+        _ if ch.is_ascii_digit() || ch == '.' => Some(CharClass::Number),
+        _ if ch.is_whitespace() => Some(CharClass::Whitespace),
+        '+' | '-' | '*' | '/' => Some(CharClass::Operator),
+        _ if ch.is_alphabetic() => Some(CharClass::Identifier),
+        '(' => Some(CharClass::ParOpen),
+        ')' => Some(CharClass::ParClose),
+        _ => None,
+    }
+}
+
+/// A cursor-style lexer that yields one [`Token`] at a time, pulled on
+/// demand instead of eagerly allocating a whole [`TokenStream`].
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
 
-        if next_state != state {
-            if let Some(token) = yield_token(state, &input, token_start, pos) {
-                tokens.push(token);
+    /// Pulls the next token, reporting [`Token::End`] once the input is
+    /// exhausted. Calling this again after exhaustion keeps yielding
+    /// `Token::End`.
+    pub fn next_token(&mut self) -> Result<(Token<'a>, Span), Error> {
+        if self.pos >= self.input.len() {
+            let span = Span::new(self.input.len(), self.input.len());
+            return Ok((Token::End, span));
+        }
+
+        let start = self.pos;
+        let mut chars = self.input[start..].char_indices();
+        let (_, first) = chars.next().unwrap();
+        let class = match classify(first) {
+            Some(class) => class,
+            None => {
+                self.pos = start + first.len_utf8();
+                return Err(Error::TokenizerError(Span::new(start, self.pos)));
             }
+        };
 
-            token_start = pos;
-            state = next_state;
+        let mut end = start + first.len_utf8();
+        if class != CharClass::ParOpen && class != CharClass::ParClose {
+            for (offset, ch) in chars {
+                if classify(ch) != Some(class) {
+                    break;
+                }
+                end = start + offset + ch.len_utf8();
+            }
         }
-    }
 
-    if let Some(token) = yield_token(state, &input, token_start, input.len()) {
-        tokens.push(token);
+        self.pos = end;
+        let span = Span::new(start, end);
+        let text = &self.input[start..end];
+        let token = match class {
+            CharClass::Number => Token::Number(text),
+            CharClass::Operator => Token::Operator(text),
+            CharClass::Identifier => Token::Identifier(text),
+            CharClass::Whitespace => Token::Whitespace(text),
+            CharClass::ParOpen => Token::ParOpen,
+            CharClass::ParClose => Token::ParClose,
+        };
+        Ok((token, span))
     }
+}
 
-    tokens.push(Token::End);
-    Ok(tokens)
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(Token<'a>, Span), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok((Token::End, _)) => None,
+            Err(err) => {
+                self.pos = self.input.len();
+                Some(Err(err))
+            }
+            other => Some(other),
+        }
+    }
 }
 
-fn yield_token(
-    current_state: TokenizerState,
-    input: &str,
-    start: usize,
-    end: usize,
-) -> Option<Token> {
-    match current_state {
-        TokenizerState::Initial => None,
-        TokenizerState::ParseNumber => Some(Token::Number(&input[start..end])),
-        TokenizerState::ParseOperator => Some(Token::Operator(&input[start..end])),
-        TokenizerState::ParseWhitespace => Some(Token::Whitespace(&input[start..end])),
-        TokenizerState::ParOpen(_) => Some(Token::ParOpen),
-        TokenizerState::ParClose(_) => Some(Token::ParClose),
+pub fn tokenize(input: &str) -> Result<TokenStream<'_>, Error> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = TokenStream::new();
+
+    loop {
+        let (token, span) = lexer.next_token()?;
+        let is_end = token == Token::End;
+        tokens.push((token, span));
+        if is_end {
+            break;
+        }
     }
+
+    Ok(tokens)
 }
 
 #[cfg(test)]
 mod tests {
     use std::vec;
 
-    use crate::tokenizer::{tokenize, Token};
+    use crate::tokenizer::{tokenize, Lexer, Token, TokenStream};
+    use crate::Span;
+
+    fn toks<'a>(stream: &TokenStream<'a>) -> Vec<Token<'a>> {
+        stream.iter().map(|(t, _)| *t).collect()
+    }
 
     #[test]
     fn test_numbers() {
         let tokens = tokenize("21 43.5 .7 0").unwrap();
         assert_eq!(
-            tokens,
+            toks(&tokens),
             vec![
                 Token::Number("21"),
                 Token::Whitespace(" "),
@@ -99,7 +156,7 @@ mod tests {
     fn test_operators() {
         let tokens = tokenize("2+3**4/5*6++//").unwrap();
         assert_eq!(
-            tokens,
+            toks(&tokens),
             vec![
                 Token::Number("2"),
                 Token::Operator("+"),
@@ -116,11 +173,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_identifiers() {
+        let tokens = tokenize("sqrt(16) sin").unwrap();
+        assert_eq!(
+            toks(&tokens),
+            vec![
+                Token::Identifier("sqrt"),
+                Token::ParOpen,
+                Token::Number("16"),
+                Token::ParClose,
+                Token::Whitespace(" "),
+                Token::Identifier("sin"),
+                Token::End,
+            ]
+        );
+    }
+
     #[test]
     fn test_parenthesis() {
         let tokens = tokenize(")(() (").unwrap();
         assert_eq!(
-            tokens,
+            toks(&tokens),
             vec![
                 Token::ParClose,
                 Token::ParOpen,
@@ -137,7 +211,7 @@ mod tests {
     fn test_whitespace() {
         let tokens = tokenize("  1 + \t2\n\n").unwrap();
         assert_eq!(
-            tokens,
+            toks(&tokens),
             vec![
                 Token::Whitespace("  "),
                 Token::Number("1"),
@@ -152,7 +226,7 @@ mod tests {
 
         let tokens = tokenize("\n\n  1 \n").unwrap();
         assert_eq!(
-            tokens,
+            toks(&tokens),
             vec![
                 Token::Whitespace("\n\n  "),
                 Token::Number("1"),
@@ -167,4 +241,123 @@ mod tests {
         assert!(tokenize("1+2+@").is_err());
         assert!(tokenize("11,3").is_err());
     }
+
+    #[test]
+    fn test_spans() {
+        let tokens = tokenize("12+3").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Number("12"), Span::new(0, 2)),
+                (Token::Operator("+"), Span::new(2, 3)),
+                (Token::Number("3"), Span::new(3, 4)),
+                (Token::End, Span::new(4, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_span() {
+        let err = tokenize("1+@").unwrap_err();
+        assert_eq!(err.span(), Some(Span::new(2, 3)));
+    }
+
+    #[test]
+    fn test_lexer_next_token() {
+        let mut lexer = Lexer::new("12+3");
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Number("12"), Span::new(0, 2))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Operator("+"), Span::new(2, 3))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Number("3"), Span::new(3, 4))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::End, Span::new(4, 4))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::End, Span::new(4, 4))
+        );
+    }
+
+    #[test]
+    fn test_lexer_iterator() {
+        let lexer = Lexer::new("1+2");
+        let tokens: Result<Vec<_>, _> = lexer.collect();
+        assert_eq!(
+            tokens.unwrap(),
+            vec![
+                (Token::Number("1"), Span::new(0, 1)),
+                (Token::Operator("+"), Span::new(1, 2)),
+                (Token::Number("2"), Span::new(2, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_next_token_advances_past_error() {
+        let mut lexer = Lexer::new("1+@+2");
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Number("1"), Span::new(0, 1))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Operator("+"), Span::new(1, 2))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap_err().span(),
+            Some(Span::new(2, 3))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Operator("+"), Span::new(3, 4))
+        );
+        assert_eq!(
+            lexer.next_token().unwrap(),
+            (Token::Number("2"), Span::new(4, 5))
+        );
+    }
+
+    #[test]
+    fn test_lexer_iterator_stops_on_error() {
+        let lexer = Lexer::new("1+@+2");
+        let items: Vec<_> = lexer.collect();
+        assert_eq!(items.len(), 3);
+        assert!(items[0].is_ok());
+        assert!(items[1].is_ok());
+        assert!(items[2].is_err());
+    }
+
+    #[test]
+    fn test_tokenize_matches_legacy_behavior() {
+        let tokens = tokenize("sqrt(16) + 2**3 - x").unwrap();
+        assert_eq!(
+            toks(&tokens),
+            vec![
+                Token::Identifier("sqrt"),
+                Token::ParOpen,
+                Token::Number("16"),
+                Token::ParClose,
+                Token::Whitespace(" "),
+                Token::Operator("+"),
+                Token::Whitespace(" "),
+                Token::Number("2"),
+                Token::Operator("**"),
+                Token::Number("3"),
+                Token::Whitespace(" "),
+                Token::Operator("-"),
+                Token::Whitespace(" "),
+                Token::Identifier("x"),
+                Token::End,
+            ]
+        );
+    }
 }